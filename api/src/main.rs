@@ -1,27 +1,314 @@
-use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use cron::Schedule;
 
-use scraper::{Campus, scrape_last_n_terms};
+use scraper::{
+    classes_from_html, ical, scrape_last_n_terms_with_options, scrape_terms_with_options, Campus, Class,
+    RetryPolicy, DEFAULT_CONCURRENCY_LIMIT,
+};
+
+#[derive(Parser)]
+#[command(name = "matrufsc3", about = "Scrapes class offerings from UFSC's CAGR")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Lists the terms currently offered on CAGR
+    ListTerms,
+
+    /// Scrapes class offerings from CAGR once
+    Scrape {
+        #[command(flatten)]
+        target: ScrapeTarget,
+    },
+
+    /// Re-runs a scrape on a cron schedule. Each tick rewrites the per-term CBOR/JSON/ICS
+    /// files with its latest results; only a `sqlite://` --out keeps earlier ticks' data
+    Watch {
+        /// Cron expression (e.g. "0 */15 * * * *" for every 15 minutes) controlling how often to scrape
+        #[arg(long = "cron")]
+        cron: String,
+
+        #[command(flatten)]
+        target: ScrapeTarget,
+    },
+
+    /// Feeds a previously-saved raw CAGR HTML page into the parser, without hitting the network
+    ParseFile {
+        /// Path to a saved CAGR response (see `ParseError` for what can go wrong here)
+        path: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+struct ScrapeTarget {
+    /// Campus to scrape; repeatable. Defaults to every campus but EAD.
+    #[arg(long = "campus", value_enum)]
+    campi: Vec<CampusArg>,
+
+    /// Term to scrape (e.g. 20241); repeatable. Conflicts with --last-n.
+    #[arg(long = "term", conflicts_with = "last_n")]
+    terms: Vec<String>,
+
+    /// Scrape the `n` most recent terms instead of specific --term values.
+    #[arg(long = "last-n")]
+    last_n: Option<usize>,
+
+    /// Directory to write the scraped output into.
+    #[arg(long = "out-dir", default_value = ".")]
+    out_dir: PathBuf,
+
+    /// Output format.
+    #[arg(long = "format", value_enum, default_value_t = Format::Cbor)]
+    format: Format,
+
+    /// Storage target overriding --out-dir/--format, e.g. `sqlite://history.db`.
+    /// Requires the `sqlite` feature.
+    #[arg(long = "out")]
+    out: Option<String>,
+
+    /// How many campus/term scrapes to run concurrently.
+    #[arg(long = "concurrency", default_value_t = DEFAULT_CONCURRENCY_LIMIT)]
+    concurrency: usize,
+
+    /// Semester start date (YYYY-MM-DD) for --format ics, overriding
+    /// `ical::term_dates`. Requires --semester-end.
+    #[arg(long = "semester-start", requires = "semester_end")]
+    semester_start: Option<NaiveDate>,
+
+    /// Semester end date (YYYY-MM-DD) for --format ics, overriding
+    /// `ical::term_dates`. Requires --semester-start.
+    #[arg(long = "semester-end", requires = "semester_start")]
+    semester_end: Option<NaiveDate>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CampusArg {
+    Ead,
+    Flo,
+    Joi,
+    Cbs,
+    Ara,
+    Bln,
+}
+
+impl From<CampusArg> for Campus {
+    fn from(campus: CampusArg) -> Self {
+        match campus {
+            CampusArg::Ead => Campus::EAD,
+            CampusArg::Flo => Campus::FLO,
+            CampusArg::Joi => Campus::JOI,
+            CampusArg::Cbs => Campus::CBS,
+            CampusArg::Ara => Campus::ARA,
+            CampusArg::Bln => Campus::BLN,
+        }
+    }
+}
+
+const DEFAULT_CAMPI: [Campus; 5] = [
+    Campus::FLO,
+    Campus::JOI,
+    Campus::CBS,
+    Campus::ARA,
+    Campus::BLN,
+];
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Cbor,
+    Json,
+    Ics,
+}
+
+fn write_classes(
+    out_dir: &Path,
+    campus: Campus,
+    term: &str,
+    classes: &[Class],
+    format: Format,
+    semester: Option<(NaiveDate, NaiveDate)>,
+) -> Result<()> {
+    let stem = out_dir.join(format!("{:?}-{}", campus, term));
+
+    match format {
+        Format::Cbor => {
+            let file = std::fs::File::create(stem.with_extension("cbor"))?;
+            serde_cbor::to_writer(file, classes)?;
+        }
+        Format::Json => {
+            let file = std::fs::File::create(stem.with_extension("json"))?;
+            serde_json::to_writer_pretty(file, classes)?;
+        }
+        Format::Ics => {
+            let ics = match semester {
+                Some((start, end)) => ical::classes_to_ical(classes, start, end),
+                None => ical::classes_to_ical_for_term(classes, term)?,
+            };
+            std::fs::write(stem.with_extension("ics"), ics)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Where a scrape's results end up: either per-term CBOR/JSON/ICS files, or
+/// a SQLite database. Shared by `scrape` (one-shot) and `watch` (repeated).
+enum Sink {
+    Files {
+        out_dir: PathBuf,
+        format: Format,
+        semester: Option<(NaiveDate, NaiveDate)>,
+    },
+    #[cfg(feature = "sqlite")]
+    Sqlite(scraper::storage::Storage),
+}
+
+impl Sink {
+    async fn new(
+        out_dir: PathBuf,
+        format: Format,
+        out: Option<String>,
+        semester: Option<(NaiveDate, NaiveDate)>,
+    ) -> Result<Self> {
+        match out {
+            None => {
+                std::fs::create_dir_all(&out_dir)?;
+                Ok(Sink::Files {
+                    out_dir,
+                    format,
+                    semester,
+                })
+            }
+            #[cfg(feature = "sqlite")]
+            Some(url) => {
+                if !url.starts_with("sqlite://") {
+                    return Err(anyhow!("--out {url} is not a sqlite:// URL"));
+                }
+                Ok(Sink::Sqlite(scraper::storage::Storage::connect(&url).await?))
+            }
+            #[cfg(not(feature = "sqlite"))]
+            Some(url) => Err(anyhow!(
+                "--out {url} requires matrufsc3 to be built with the \"sqlite\" feature"
+            )),
+        }
+    }
+
+    async fn write(&self, campus: Campus, term: &str, classes: &[Class]) -> Result<()> {
+        if classes.is_empty() {
+            return Ok(());
+        }
+
+        match self {
+            Sink::Files {
+                out_dir,
+                format,
+                semester,
+            } => write_classes(out_dir, campus, term, classes, *format, *semester),
+            #[cfg(feature = "sqlite")]
+            Sink::Sqlite(storage) => storage.store(campus, term, classes).await,
+        }
+    }
+}
+
+fn resolve_campi(campi: Vec<CampusArg>) -> Vec<Campus> {
+    if campi.is_empty() {
+        DEFAULT_CAMPI.to_vec()
+    } else {
+        campi.into_iter().map(Campus::from).collect()
+    }
+}
+
+async fn run_scrape(
+    campi: &[Campus],
+    terms: &[String],
+    last_n: Option<usize>,
+    concurrency_limit: usize,
+) -> Result<Vec<Result<(Campus, String, Vec<Class>)>>> {
+    match last_n {
+        Some(n) => {
+            scrape_last_n_terms_with_options(n, campi, RetryPolicy::default(), concurrency_limit).await
+        }
+        None if !terms.is_empty() => Ok(scrape_terms_with_options(
+            terms,
+            campi,
+            RetryPolicy::default(),
+            concurrency_limit,
+        )
+        .await),
+        None => Err(anyhow!("must pass either --term or --last-n")),
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let selected_campi = [
-        Campus::FLO,
-        Campus::JOI,
-        Campus::CBS,
-        Campus::ARA,
-        Campus::BLN,
-    ];
-
-    let datasets = scrape_last_n_terms(3, &selected_campi).await;
-
-    for dataset in datasets {
-        let (campus, term, classes) = dataset?;
-
-        if !classes.is_empty() {
-            let file = File::create(&format!("{:?}-{}.cbor", campus, term))?;
-            serde_cbor::to_writer(file, &classes)?;
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::ListTerms => {
+            for term in scraper::available_terms().await? {
+                println!("{term}");
+            }
+        }
+
+        Command::Scrape { target } => {
+            let campi = resolve_campi(target.campi);
+            let semester = target.semester_start.zip(target.semester_end);
+            let datasets = run_scrape(&campi, &target.terms, target.last_n, target.concurrency).await?;
+            let sink = Sink::new(target.out_dir, target.format, target.out, semester).await?;
+
+            for dataset in datasets {
+                let (campus, term, classes) = dataset?;
+                sink.write(campus, &term, &classes).await?;
+            }
+        }
+
+        Command::Watch { cron, target } => {
+            let schedule = Schedule::from_str(&cron)?;
+            let campi = resolve_campi(target.campi);
+            let semester = target.semester_start.zip(target.semester_end);
+            let sink = Sink::new(target.out_dir, target.format, target.out, semester).await?;
+
+            loop {
+                let Some(next) = schedule.upcoming(Utc).next() else {
+                    return Err(anyhow!("cron expression \"{cron}\" has no upcoming fire times"));
+                };
+
+                let until_next = (next - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+                tokio::time::sleep(until_next).await;
+
+                let datasets = match run_scrape(&campi, &target.terms, target.last_n, target.concurrency).await {
+                    Ok(datasets) => datasets,
+                    Err(err) => {
+                        eprintln!("[{}] tick failed: {err:#}", Utc::now());
+                        continue;
+                    }
+                };
+
+                for dataset in datasets {
+                    match dataset {
+                        Ok((campus, term, classes)) => match sink.write(campus, &term, &classes).await {
+                            Ok(()) => println!("[{}] scraped {:?} {}", Utc::now(), campus, term),
+                            Err(err) => eprintln!("[{}] failed to store {:?} {}: {err:#}", Utc::now(), campus, term),
+                        },
+                        Err(err) => eprintln!("[{}] scrape failed: {err:#}", Utc::now()),
+                    }
+                }
+            }
+        }
+
+        Command::ParseFile { path } => {
+            let contents = std::fs::read_to_string(&path)?;
+            let classes = classes_from_html(&contents)?;
+            println!("parsed {} classes", classes.len());
+            println!("{}", serde_json::to_string_pretty(&classes)?);
         }
     }
 
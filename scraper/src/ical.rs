@@ -0,0 +1,200 @@
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use thiserror::Error;
+
+use crate::parse::{Class, Time};
+
+#[derive(Debug, Error)]
+pub enum IcalError {
+    #[error("no known semester start/end dates for term {0}")]
+    UnknownTerm(String),
+}
+
+// UFSC "periodos" are 50 minutes each; a class's duration is `credits * 50min`.
+const MINUTES_PER_CREDIT: i64 = 50;
+
+/// Semester start/end dates for terms we already know about, keyed by the
+/// CAGR term string (e.g. "20241"). CAGR itself never tells us these dates,
+/// so callers scraping a term not listed here must supply them explicitly
+/// via `matrufsc3 scrape --semester-start/--semester-end`, or call
+/// [`classes_to_ical`] directly.
+pub fn term_dates(term: &str) -> Option<(NaiveDate, NaiveDate)> {
+    match term {
+        "20241" => Some((
+            NaiveDate::from_ymd_opt(2024, 3, 11).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 7, 19).unwrap(),
+        )),
+        "20242" => Some((
+            NaiveDate::from_ymd_opt(2024, 8, 5).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 12, 13).unwrap(),
+        )),
+        "20251" => Some((
+            NaiveDate::from_ymd_opt(2025, 3, 10).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 7, 18).unwrap(),
+        )),
+        "20252" => Some((
+            NaiveDate::from_ymd_opt(2025, 8, 4).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 12, 12).unwrap(),
+        )),
+        _ => None,
+    }
+}
+
+/// Looks up `term`'s dates in [`term_dates`] before delegating to [`classes_to_ical`].
+pub fn classes_to_ical_for_term(classes: &[Class], term: &str) -> Result<String> {
+    let (start, end) = term_dates(term).ok_or_else(|| IcalError::UnknownTerm(term.to_owned()))?;
+    Ok(classes_to_ical(classes, start, end))
+}
+
+fn first_occurrence_on_or_after(date: NaiveDate, weekday: chrono::Weekday) -> NaiveDate {
+    let days_ahead = (7 + weekday.num_days_from_sunday() as i64
+        - date.weekday().num_days_from_sunday() as i64)
+        % 7;
+    date + Duration::days(days_ahead)
+}
+
+fn escape_text(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn uid_for(class: &Class, time: &Time) -> String {
+    format!(
+        "{}-{:?}-{}@matrufsc3",
+        class.id,
+        time.weekday,
+        time.time.format("%H%M")
+    )
+}
+
+/// Folds a content line at 75 octets per RFC 5545 §3.1: continuation lines
+/// are introduced by a CRLF followed by a single space. `line` must not
+/// already contain a line break.
+fn fold_line(line: &str) -> String {
+    const LIMIT: usize = 75;
+
+    if line.len() <= LIMIT {
+        return line.to_owned();
+    }
+
+    let mut folded = String::new();
+    let mut start = 0;
+    let mut first = true;
+
+    while start < line.len() {
+        let limit = if first { LIMIT } else { LIMIT - 1 };
+        let mut end = (start + limit).min(line.len());
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        if !first {
+            folded.push_str("\r\n ");
+        }
+        folded.push_str(&line[start..end]);
+
+        start = end;
+        first = false;
+    }
+
+    folded
+}
+
+fn event_for_time(class: &Class, time: &Time, term_start: NaiveDate, term_end: NaiveDate, dtstamp: &str) -> String {
+    let dtstart = NaiveDateTime::new(
+        first_occurrence_on_or_after(term_start, time.weekday),
+        time.time,
+    );
+    let until = NaiveDateTime::new(term_end, time.time);
+    let duration_minutes = i64::from(time.credits) * MINUTES_PER_CREDIT;
+
+    let summary = escape_text(&format!("{} ({})", class.course.title, class.id));
+    let location = escape_text(&time.place);
+    let description = escape_text(&format!(
+        "{}\n{}/{} matriculados\nProfessores: {}",
+        class.labels.join(", "),
+        class.enrolled,
+        class.capacity,
+        class.professors.join(", "),
+    ));
+
+    let lines = [
+        "BEGIN:VEVENT".to_owned(),
+        format!("UID:{}", uid_for(class, time)),
+        format!("DTSTAMP:{dtstamp}"),
+        format!("DTSTART:{}", dtstart.format("%Y%m%dT%H%M%S")),
+        format!("DURATION:PT{duration_minutes}M"),
+        format!("RRULE:FREQ=WEEKLY;UNTIL={}", until.format("%Y%m%dT%H%M%S")),
+        format!("SUMMARY:{summary}"),
+        format!("LOCATION:{location}"),
+        format!("DESCRIPTION:{description}"),
+        "END:VEVENT".to_owned(),
+    ];
+
+    lines
+        .into_iter()
+        .map(|line| fold_line(&line) + "\r\n")
+        .collect()
+}
+
+/// Renders `classes` as an RFC 5545 calendar, with one `VEVENT` per
+/// `(class, time)` pair, recurring weekly from `term_start` to `term_end`.
+pub fn classes_to_ical(classes: &[Class], term_start: NaiveDate, term_end: NaiveDate) -> String {
+    let dtstamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut calendar =
+        String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//matrufsc3//scraper//PT\r\nCALSCALE:GREGORIAN\r\n");
+
+    for class in classes {
+        for time in &class.times {
+            calendar.push_str(&event_for_time(class, time, term_start, term_end, &dtstamp));
+        }
+    }
+
+    calendar.push_str("END:VCALENDAR\r\n");
+    calendar
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Weekday;
+
+    use super::*;
+
+    #[test]
+    fn first_occurrence_on_or_after_same_weekday_stays_put() {
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        assert_eq!(first_occurrence_on_or_after(monday, Weekday::Mon), monday);
+    }
+
+    #[test]
+    fn first_occurrence_on_or_after_advances_to_next_matching_weekday() {
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        let thursday = NaiveDate::from_ymd_opt(2024, 3, 14).unwrap();
+        assert_eq!(first_occurrence_on_or_after(monday, Weekday::Thu), thursday);
+    }
+
+    #[test]
+    fn first_occurrence_on_or_after_wraps_to_the_following_week() {
+        // classes starting on a Friday looking for a Thursday session must
+        // land on the *next* Thursday, not the one just before `date`
+        let friday = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let next_thursday = NaiveDate::from_ymd_opt(2024, 3, 21).unwrap();
+        assert_eq!(first_occurrence_on_or_after(friday, Weekday::Thu), next_thursday);
+    }
+
+    #[test]
+    fn escape_text_escapes_reserved_characters() {
+        assert_eq!(
+            escape_text("Prova; Trabalho, revisão\nfinal\\"),
+            "Prova\\; Trabalho\\, revisão\\nfinal\\\\"
+        );
+    }
+
+    #[test]
+    fn escape_text_leaves_plain_text_untouched() {
+        assert_eq!(escape_text("Sala CTC-101"), "Sala CTC-101");
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
-use futures::{stream::FuturesUnordered, StreamExt, TryFutureExt};
+use futures::{stream, StreamExt, TryFutureExt};
 use reqwest::Client;
 use select::{
     document::Document,
@@ -9,7 +9,15 @@ use select::{
 };
 
 mod parse;
-use parse::Class;
+pub use parse::{classes_from_html, Class};
+
+pub mod ical;
+
+mod retry;
+pub use retry::RetryPolicy;
+
+#[cfg(feature = "sqlite")]
+pub mod storage;
 
 const CAGR_URL: &str = "https://cagr.sistemas.ufsc.br/modules/comunidade/cadastroTurmas/";
 
@@ -54,6 +62,7 @@ pub struct Cagr {
     client: Client,
     campus: Campus,
     term: String,
+    retry_policy: RetryPolicy,
 }
 
 // why use a struct? because the http client needs to be in a specific state
@@ -62,25 +71,31 @@ pub struct Cagr {
 
 impl Cagr {
     pub async fn new(campus: Campus, term: String) -> Result<Self> {
+        Self::with_retry_policy(campus, term, RetryPolicy::default()).await
+    }
+
+    pub async fn with_retry_policy(campus: Campus, term: String, retry_policy: RetryPolicy) -> Result<Self> {
         let client = Client::builder().cookie_store(true).build()?;
 
         // the site requires the client to be primed with the cookies
-        client.post(CAGR_URL).send().await?;
+        retry::send_with_retry(&retry_policy, client.post(CAGR_URL)).await?;
 
         Ok(Self {
             client,
             campus,
             term,
+            retry_policy,
         })
     }
 
     pub async fn page_count(&self) -> Result<usize> {
-        let response = self
-            .client
-            .post(CAGR_URL)
-            .form(&form_data(self.campus, self.term.clone(), 2)) // page 1 does not work
-            .send()
-            .await?;
+        let response = retry::send_with_retry(
+            &self.retry_policy,
+            self.client
+                .post(CAGR_URL)
+                .form(&form_data(self.campus, self.term.clone(), 2)), // page 1 does not work
+        )
+        .await?;
 
         let contents = response.text().await?;
         let document = Document::from(contents.as_ref());
@@ -105,12 +120,13 @@ impl Cagr {
 
         let mut previous: Option<String> = None;
         for page_index in 1..=self.page_count().await? {
-            let response = self
-                .client
-                .post(CAGR_URL)
-                .form(&form_data(self.campus, self.term.clone(), page_index))
-                .send()
-                .await?;
+            let response = retry::send_with_retry(
+                &self.retry_policy,
+                self.client
+                    .post(CAGR_URL)
+                    .form(&form_data(self.campus, self.term.clone(), page_index)),
+            )
+            .await?;
 
             // TODO: check response url
 
@@ -131,7 +147,11 @@ impl Cagr {
 }
 
 pub async fn available_terms() -> Result<Vec<String>> {
-    let response = reqwest::get(CAGR_URL).await?;
+    available_terms_with_retry_policy(&RetryPolicy::default()).await
+}
+
+async fn available_terms_with_retry_policy(retry_policy: &RetryPolicy) -> Result<Vec<String>> {
+    let response = retry::send_with_retry(retry_policy, Client::new().get(CAGR_URL)).await?;
     let contents = response.text().await?;
     let document = Document::from(contents.as_ref());
 
@@ -145,24 +165,79 @@ pub async fn available_terms() -> Result<Vec<String>> {
         .collect())
 }
 
+/// How many campus/term scrapes are allowed to run at once by default; large
+/// multi-term crawls should lower this to stay polite to the CAGR server.
+pub const DEFAULT_CONCURRENCY_LIMIT: usize = 4;
+
 pub async fn scrape_last_n_terms(
     n: usize,
     campi: &[Campus],
-) -> Vec<Result<(Campus, String, Vec<Class>)>> {
-    let terms = available_terms()
-        .await
-        .unwrap()
+) -> Result<Vec<Result<(Campus, String, Vec<Class>)>>> {
+    scrape_last_n_terms_with_options(
+        n,
+        campi,
+        RetryPolicy::default(),
+        DEFAULT_CONCURRENCY_LIMIT,
+    )
+    .await
+}
+
+pub async fn scrape_last_n_terms_with_retry_policy(
+    n: usize,
+    campi: &[Campus],
+    retry_policy: RetryPolicy,
+) -> Result<Vec<Result<(Campus, String, Vec<Class>)>>> {
+    scrape_last_n_terms_with_options(n, campi, retry_policy, DEFAULT_CONCURRENCY_LIMIT).await
+}
+
+pub async fn scrape_last_n_terms_with_options(
+    n: usize,
+    campi: &[Campus],
+    retry_policy: RetryPolicy,
+    concurrency_limit: usize,
+) -> Result<Vec<Result<(Campus, String, Vec<Class>)>>> {
+    // listing terms failing is just as transient as any other CAGR request,
+    // so it goes through the same retry policy instead of panicking the caller
+    let terms = available_terms_with_retry_policy(&retry_policy)
+        .await?
         .into_iter()
         .take(n)
         .collect::<Vec<_>>();
 
-    let tasks = FuturesUnordered::new();
+    Ok(scrape_many(jobs_for(&terms, campi), retry_policy, concurrency_limit).await)
+}
 
-    for term in terms {
-        for &campus in campi {
-            tasks.push(Cagr::new(campus, term.clone()).and_then(Cagr::scrape));
-        }
-    }
+pub async fn scrape_terms(
+    terms: &[String],
+    campi: &[Campus],
+) -> Vec<Result<(Campus, String, Vec<Class>)>> {
+    scrape_terms_with_options(terms, campi, RetryPolicy::default(), DEFAULT_CONCURRENCY_LIMIT).await
+}
+
+pub async fn scrape_terms_with_options(
+    terms: &[String],
+    campi: &[Campus],
+    retry_policy: RetryPolicy,
+    concurrency_limit: usize,
+) -> Vec<Result<(Campus, String, Vec<Class>)>> {
+    scrape_many(jobs_for(terms, campi), retry_policy, concurrency_limit).await
+}
 
-    tasks.collect().await
+fn jobs_for(terms: &[String], campi: &[Campus]) -> Vec<(Campus, String)> {
+    terms
+        .iter()
+        .flat_map(|term| campi.iter().map(move |&campus| (campus, term.clone())))
+        .collect()
+}
+
+async fn scrape_many(
+    jobs: Vec<(Campus, String)>,
+    retry_policy: RetryPolicy,
+    concurrency_limit: usize,
+) -> Vec<Result<(Campus, String, Vec<Class>)>> {
+    stream::iter(jobs)
+        .map(|(campus, term)| Cagr::with_retry_policy(campus, term, retry_policy).and_then(Cagr::scrape))
+        .buffer_unordered(concurrency_limit)
+        .collect()
+        .await
 }
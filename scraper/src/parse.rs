@@ -12,29 +12,29 @@ use thiserror::Error;
 
 #[derive(Serialize)]
 pub struct Course {
-    id: String,
-    title: String,
-    credits: u32,
+    pub(crate) id: String,
+    pub(crate) title: String,
+    pub(crate) credits: u32,
 }
 
 #[derive(Serialize)]
 pub struct Class {
-    id: String,
-    course: Course,
-    labels: Vec<String>,
-    capacity: u32,
-    enrolled: u32,
-    waiting: u32,
-    times: Vec<Time>,
-    professors: Vec<String>,
+    pub(crate) id: String,
+    pub(crate) course: Course,
+    pub(crate) labels: Vec<String>,
+    pub(crate) capacity: u32,
+    pub(crate) enrolled: u32,
+    pub(crate) waiting: u32,
+    pub(crate) times: Vec<Time>,
+    pub(crate) professors: Vec<String>,
 }
 
 #[derive(Serialize)]
 pub struct Time {
-    weekday: Weekday,
-    time: NaiveTime,
-    credits: u32,
-    place: String,
+    pub(crate) weekday: Weekday,
+    pub(crate) time: NaiveTime,
+    pub(crate) credits: u32,
+    pub(crate) place: String,
 }
 
 #[derive(Debug, Error)]
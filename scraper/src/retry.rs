@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+
+/// Backoff/jitter policy applied to every CAGR request: on a connection
+/// error, timeout, or 5xx/429 response, we wait `base_delay * 2^attempt`
+/// (capped at `max_delay`, then scaled by a random factor in `[0.5, 1.0]`
+/// so we never retry at less than half the computed delay) before trying
+/// again, up to `max_retries` times.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub max_retries: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_retries: 5,
+        }
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn is_retryable_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+fn capped_exponential_delay(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let exponential = policy.base_delay.saturating_mul(1u32 << attempt.min(31));
+    exponential.min(policy.max_delay)
+}
+
+async fn backoff(policy: &RetryPolicy, attempt: u32) {
+    let delay = capped_exponential_delay(policy, attempt);
+    // jitter scaled to [0.5, 1.0] so we always wait at least half of `delay`,
+    // rather than risking a near-instant retry against a rate-limited server
+    let jitter = rand::thread_rng().gen_range(0.5..=1.0);
+    tokio::time::sleep(delay.mul_f64(jitter)).await;
+}
+
+/// Sends `request`, retrying transient failures according to `policy`.
+///
+/// `request` is re-issued from scratch on every attempt via
+/// [`RequestBuilder::try_clone`], which only fails for streaming bodies;
+/// CAGR requests are always built from an in-memory form, so this never panics.
+pub(crate) async fn send_with_retry(policy: &RetryPolicy, request: RequestBuilder) -> Result<Response> {
+    let mut attempt = 0;
+
+    loop {
+        let this_attempt = request
+            .try_clone()
+            .expect("CAGR requests are built from an in-memory form, so they are always clonable");
+
+        match this_attempt.send().await {
+            Ok(response) if is_retryable_status(response.status()) && attempt < policy.max_retries => {
+                backoff(policy, attempt).await;
+                attempt += 1;
+            }
+            Ok(response) => return Ok(response),
+            Err(err) if is_retryable_error(&err) && attempt < policy.max_retries => {
+                backoff(policy, attempt).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capped_exponential_delay_doubles_each_attempt() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(60),
+            max_retries: 5,
+        };
+
+        assert_eq!(capped_exponential_delay(&policy, 0), Duration::from_millis(100));
+        assert_eq!(capped_exponential_delay(&policy, 1), Duration::from_millis(200));
+        assert_eq!(capped_exponential_delay(&policy, 2), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn capped_exponential_delay_never_exceeds_max_delay() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(5),
+            max_retries: 10,
+        };
+
+        assert_eq!(capped_exponential_delay(&policy, 10), Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,183 @@
+//! SQLite-backed persistence for scraped classes.
+//!
+//! Unlike the CBOR/JSON/ICS writers, which clobber a file on every run,
+//! [`Storage`] upserts into normalized tables keyed by `(campus, term, class
+//! id)` and appends a timestamped row to `enrollment_snapshots` on every
+//! call to [`Storage::store`], so repeated scrapes build a time series of
+//! how fast a class filled up.
+
+use std::str::FromStr;
+
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Pool, Sqlite};
+
+use crate::{Campus, Class};
+
+pub struct Storage {
+    pool: Pool<Sqlite>,
+}
+
+impl Storage {
+    /// Opens (and creates, if missing) the SQLite database at `url`, e.g.
+    /// `sqlite://history.db`.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+        let storage = Self { pool };
+        storage.migrate().await?;
+        Ok(storage)
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS courses (
+                id TEXT NOT NULL PRIMARY KEY,
+                title TEXT NOT NULL,
+                credits INTEGER NOT NULL
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS classes (
+                campus TEXT NOT NULL,
+                term TEXT NOT NULL,
+                id TEXT NOT NULL,
+                course_id TEXT NOT NULL REFERENCES courses(id),
+                labels TEXT NOT NULL,
+                PRIMARY KEY (campus, term, id)
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS times (
+                campus TEXT NOT NULL,
+                term TEXT NOT NULL,
+                class_id TEXT NOT NULL,
+                weekday TEXT NOT NULL,
+                time TEXT NOT NULL,
+                credits INTEGER NOT NULL,
+                place TEXT NOT NULL,
+                PRIMARY KEY (campus, term, class_id, weekday, time),
+                FOREIGN KEY (campus, term, class_id) REFERENCES classes(campus, term, id)
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS professors (
+                campus TEXT NOT NULL,
+                term TEXT NOT NULL,
+                class_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                PRIMARY KEY (campus, term, class_id, name),
+                FOREIGN KEY (campus, term, class_id) REFERENCES classes(campus, term, id)
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS enrollment_snapshots (
+                campus TEXT NOT NULL,
+                term TEXT NOT NULL,
+                class_id TEXT NOT NULL,
+                captured_at TEXT NOT NULL,
+                enrolled INTEGER NOT NULL,
+                waiting INTEGER NOT NULL,
+                capacity INTEGER NOT NULL,
+                FOREIGN KEY (campus, term, class_id) REFERENCES classes(campus, term, id)
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Upserts `classes` scraped for `(campus, term)` and appends an
+    /// enrollment snapshot timestamped with the current time for each one.
+    pub async fn store(&self, campus: Campus, term: &str, classes: &[Class]) -> Result<()> {
+        let campus = campus.to_string();
+        let captured_at = Utc::now().to_rfc3339();
+
+        for class in classes {
+            sqlx::query(
+                "INSERT INTO courses (id, title, credits) VALUES (?, ?, ?)
+                 ON CONFLICT (id) DO UPDATE SET title = excluded.title, credits = excluded.credits",
+            )
+            .bind(&class.course.id)
+            .bind(&class.course.title)
+            .bind(i64::from(class.course.credits))
+            .execute(&self.pool)
+            .await?;
+
+            sqlx::query(
+                "INSERT INTO classes (campus, term, id, course_id, labels) VALUES (?, ?, ?, ?, ?)
+                 ON CONFLICT (campus, term, id)
+                 DO UPDATE SET course_id = excluded.course_id, labels = excluded.labels",
+            )
+            .bind(&campus)
+            .bind(term)
+            .bind(&class.id)
+            .bind(&class.course.id)
+            .bind(class.labels.join(","))
+            .execute(&self.pool)
+            .await?;
+
+            for time in &class.times {
+                sqlx::query(
+                    "INSERT INTO times (campus, term, class_id, weekday, time, credits, place)
+                     VALUES (?, ?, ?, ?, ?, ?, ?)
+                     ON CONFLICT (campus, term, class_id, weekday, time)
+                     DO UPDATE SET credits = excluded.credits, place = excluded.place",
+                )
+                .bind(&campus)
+                .bind(term)
+                .bind(&class.id)
+                .bind(time.weekday.to_string())
+                .bind(time.time.to_string())
+                .bind(i64::from(time.credits))
+                .bind(&time.place)
+                .execute(&self.pool)
+                .await?;
+            }
+
+            for professor in &class.professors {
+                sqlx::query(
+                    "INSERT INTO professors (campus, term, class_id, name) VALUES (?, ?, ?, ?)
+                     ON CONFLICT (campus, term, class_id, name) DO NOTHING",
+                )
+                .bind(&campus)
+                .bind(term)
+                .bind(&class.id)
+                .bind(professor)
+                .execute(&self.pool)
+                .await?;
+            }
+
+            sqlx::query(
+                "INSERT INTO enrollment_snapshots
+                    (campus, term, class_id, captured_at, enrolled, waiting, capacity)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&campus)
+            .bind(term)
+            .bind(&class.id)
+            .bind(&captured_at)
+            .bind(i64::from(class.enrolled))
+            .bind(i64::from(class.waiting))
+            .bind(i64::from(class.capacity))
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+}